@@ -0,0 +1,300 @@
+//! Lowers a resolved `abstract::Program` into HVM interaction-combinator terms.
+//!
+//! This is the crate's execution target: analysis stops at a `Program` with scope and name
+//! resolution, and this module turns that into something HVM can actually run. A free
+//! identifier is reported as a codegen error rather than emitted as a term — by the time a
+//! program reaches here it's already gone through `ScopeTracker`, so a free identifier means
+//! it resolved to nothing defined, not something analysis missed.
+
+use std::collections::HashMap;
+
+use crate::{
+    errors::Error,
+    r#abstract::{Def, Expr, ExprKind, Program, TopLevelKind},
+    span::Span,
+};
+
+/// Lowers every `def` in `program` to a named HVM rule, inlining references to `visible` (the
+/// unqualified and qualified names already lowered from its import closure) by name.
+///
+/// Returns one error per identifier that resolves to neither a bound variable, a local `def`,
+/// nor an entry in `visible`.
+pub fn lower_program(
+    program: &Program,
+    visible: &HashMap<String, hvm::Term>,
+) -> Result<HashMap<String, hvm::Term>, Vec<Error>> {
+    let mut rules = HashMap::new();
+    let mut errors = Vec::new();
+
+    // Every def in the file is resolvable from any other, whatever their order.
+    let locals: HashMap<String, hvm::Term> = program
+        .vec
+        .iter()
+        .filter_map(|top_level| match &top_level.data {
+            TopLevelKind::Def(def) => {
+                Some((def.name.data.clone(), hvm::Term::Ref(def.name.data.clone())))
+            }
+            _ => None,
+        })
+        .collect();
+
+    for top_level in &program.vec {
+        if let TopLevelKind::Def(def) = &top_level.data {
+            match lower_def(def, visible, &locals) {
+                Ok(term) => {
+                    rules.insert(def.name.data.clone(), term);
+                }
+                Err(mut def_errors) => errors.append(&mut def_errors),
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(rules)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Lowers one `def` to its rule body: a chain of `Lam`s over its parameters wrapping the
+/// lowered expression.
+fn lower_def(
+    def: &Def,
+    visible: &HashMap<String, hvm::Term>,
+    locals: &HashMap<String, hvm::Term>,
+) -> Result<hvm::Term, Vec<Error>> {
+    let mut bound: Vec<String> = def.params.iter().map(|p| p.data.clone()).collect();
+    let body = lower_expr(&def.body, visible, locals, &mut bound)?;
+
+    Ok(def.params.iter().rev().fold(body, |acc, param| hvm::Term::Lam {
+        name: param.data.clone(),
+        body: Box::new(acc),
+    }))
+}
+
+/// Lowers a single expression. `bound` tracks the lambda-bound names currently in scope, so a
+/// `Var` resolves first against `bound`, then `locals` (this file's own defs), then `visible`
+/// (inlined imports), and is only a codegen error once none of those apply.
+fn lower_expr(
+    expr: &Expr,
+    visible: &HashMap<String, hvm::Term>,
+    locals: &HashMap<String, hvm::Term>,
+    bound: &mut Vec<String>,
+) -> Result<hvm::Term, Vec<Error>> {
+    match &expr.kind {
+        ExprKind::Number(n) => Ok(hvm::Term::U60(*n)),
+        ExprKind::String(s) => Ok(string_term(s)),
+        ExprKind::Quote(inner) => Ok(quote_term(inner)),
+        ExprKind::Var(name) => resolve_var(name, &expr.span, visible, locals, bound),
+        ExprKind::Lambda(params, body) => {
+            let mut inner_bound = bound.clone();
+            inner_bound.extend(params.iter().map(|p| p.data.clone()));
+            let body = lower_expr(body, visible, locals, &mut inner_bound)?;
+
+            Ok(params.iter().rev().fold(body, |acc, param| hvm::Term::Lam {
+                name: param.data.clone(),
+                body: Box::new(acc),
+            }))
+        }
+        ExprKind::App(callee, args) => {
+            let mut errors = Vec::new();
+
+            let callee_term = match lower_expr(callee, visible, locals, bound) {
+                Ok(term) => Some(term),
+                Err(mut callee_errors) => {
+                    errors.append(&mut callee_errors);
+                    None
+                }
+            };
+
+            let mut arg_terms = Vec::new();
+            for arg in args {
+                match lower_expr(arg, visible, locals, bound) {
+                    Ok(term) => arg_terms.push(term),
+                    Err(mut arg_errors) => errors.append(&mut arg_errors),
+                }
+            }
+
+            if !errors.is_empty() {
+                return Err(errors);
+            }
+
+            Ok(arg_terms.into_iter().fold(callee_term.unwrap(), |acc, arg| hvm::Term::App {
+                func: Box::new(acc),
+                arg: Box::new(arg),
+            }))
+        }
+    }
+}
+
+/// Resolves a `Var` in precedence order: lambda-bound, then a local definition, then an
+/// inlined import. Anything else is a free identifier and becomes a codegen error.
+fn resolve_var(
+    name: &str,
+    span: &Span,
+    visible: &HashMap<String, hvm::Term>,
+    locals: &HashMap<String, hvm::Term>,
+    bound: &[String],
+) -> Result<hvm::Term, Vec<Error>> {
+    if bound.iter().any(|b| b == name) {
+        return Ok(hvm::Term::Var(name.to_string()));
+    }
+    if locals.contains_key(name) || visible.contains_key(name) {
+        return Ok(hvm::Term::Ref(name.to_string()));
+    }
+
+    Err(vec![Error::new(
+        format!("cannot find variable `{name}` during codegen."),
+        span.clone(),
+    )
+    .with_code("firefly::codegen-unbound")])
+}
+
+/// Lowers a string literal to HVM's usual encoding for strings: a right-folded `Cons` list of
+/// `U60` char codes, terminated by a nullary `Nil`.
+fn string_term(s: &str) -> hvm::Term {
+    s.chars().rev().fold(
+        hvm::Term::Ctr {
+            name: "Nil".to_string(),
+            args: Vec::new(),
+        },
+        |tail, ch| hvm::Term::Ctr {
+            name: "Cons".to_string(),
+            args: vec![hvm::Term::U60(ch as u64), tail],
+        },
+    )
+}
+
+/// Lowers a quoted expression to its constructor-tree representation instead of evaluating
+/// it, matching how quoted data stays inert until explicitly run. Numbers and strings lower
+/// as their plain literal terms; every other case gets its own constructor that mirrors the
+/// expression's own shape — `Lambda` and `Quote` included — so quoting one doesn't lose
+/// information a consumer of the quoted data would need back.
+fn quote_term(expr: &Expr) -> hvm::Term {
+    match &expr.kind {
+        ExprKind::Number(n) => hvm::Term::U60(*n),
+        ExprKind::String(s) => string_term(s),
+        ExprKind::Var(name) => hvm::Term::Ctr {
+            name: "Sym".to_string(),
+            args: vec![string_term(name)],
+        },
+        ExprKind::App(callee, args) => {
+            let mut items = vec![quote_term(callee)];
+            items.extend(args.iter().map(quote_term));
+            hvm::Term::Ctr {
+                name: "List".to_string(),
+                args: items,
+            }
+        }
+        ExprKind::Lambda(params, body) => {
+            let params = params
+                .iter()
+                .map(|param| hvm::Term::Ctr {
+                    name: "Sym".to_string(),
+                    args: vec![string_term(&param.data)],
+                })
+                .collect();
+
+            hvm::Term::Ctr {
+                name: "Lambda".to_string(),
+                args: vec![
+                    hvm::Term::Ctr {
+                        name: "List".to_string(),
+                        args: params,
+                    },
+                    quote_term(body),
+                ],
+            }
+        }
+        ExprKind::Quote(inner) => hvm::Term::Ctr {
+            name: "Quote".to_string(),
+            args: vec![quote_term(inner)],
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::{lower_program, quote_term};
+    use crate::{
+        r#abstract::{Def, Expr, ExprKind, Program, TopLevelKind},
+        span::{Span, Spanned},
+    };
+
+    fn expr(kind: ExprKind) -> Expr {
+        Expr {
+            kind,
+            span: Span::empty(),
+        }
+    }
+
+    #[test]
+    fn quote_term_preserves_lambda_params_and_body() {
+        let lambda = expr(ExprKind::Lambda(
+            vec![Spanned::new("x".to_string(), Span::empty())],
+            Box::new(expr(ExprKind::Var("x".to_string()))),
+        ));
+
+        match quote_term(&lambda) {
+            hvm::Term::Ctr { name, args } => {
+                assert_eq!(name, "Lambda");
+                assert_eq!(args.len(), 2);
+                assert!(matches!(&args[0], hvm::Term::Ctr { name, .. } if name == "List"));
+                assert!(matches!(&args[1], hvm::Term::Ctr { name, .. } if name == "Sym"));
+            }
+            other => panic!("expected a Lambda constructor, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn quote_term_preserves_nested_quote() {
+        let quoted = expr(ExprKind::Quote(Box::new(expr(ExprKind::Number(1)))));
+
+        match quote_term(&quoted) {
+            hvm::Term::Ctr { name, args } => {
+                assert_eq!(name, "Quote");
+                assert_eq!(args.len(), 1);
+                assert!(matches!(args[0], hvm::Term::U60(1)));
+            }
+            other => panic!("expected a Quote constructor, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn lower_program_reports_a_free_identifier_as_a_codegen_error() {
+        let program = Program {
+            vec: vec![Spanned::new(
+                TopLevelKind::Def(Def {
+                    name: Spanned::new("main".to_string(), Span::empty()),
+                    params: Vec::new(),
+                    body: expr(ExprKind::Var("undefined".to_string())),
+                }),
+                Span::empty(),
+            )],
+        };
+
+        let errors = lower_program(&program, &HashMap::new()).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].code, "firefly::codegen-unbound");
+    }
+
+    #[test]
+    fn lower_program_inlines_a_visible_import_by_reference() {
+        let visible = HashMap::from([("helper".to_string(), hvm::Term::U60(0))]);
+        let program = Program {
+            vec: vec![Spanned::new(
+                TopLevelKind::Def(Def {
+                    name: Spanned::new("main".to_string(), Span::empty()),
+                    params: Vec::new(),
+                    body: expr(ExprKind::Var("helper".to_string())),
+                }),
+                Span::empty(),
+            )],
+        };
+
+        let rules = lower_program(&program, &visible).unwrap();
+        assert!(matches!(rules.get("main"), Some(hvm::Term::Ref(name)) if name == "helper"));
+    }
+}
@@ -13,7 +13,9 @@ use crate::{
     errors::Error,
     file::File,
     id::{self, Id},
-    parser::parse,
+    codegen::lower_program,
+    parser::{parse, reparse_incremental},
+    query::{Cycle, Definition, Filter, Query, QueryResult},
     r#abstract::{Program, TopLevelKind},
     relation::Relations,
     span::{Point, Span, Spanned},
@@ -78,7 +80,12 @@ impl Env {
         Some(())
     }
 
-    /// Applies edits to an existing file.
+    /// Applies edits to an existing file, reparsing incrementally where possible.
+    ///
+    /// For a single contiguous edit, this reuses the previous [`SyntaxNode`] via
+    /// [`reparse_incremental`] instead of rebuilding the whole tree; anything else (multiple
+    /// edits in one call, or a subtree that can't be reused in place) falls back to a full
+    /// [`parse`].
     pub async fn apply_edits(
         &mut self,
         id: Id<id::File>,
@@ -89,9 +96,24 @@ impl Env {
         for edit in edits {
             let start = edit.span.start.to_offset(&file.source);
             let end = edit.span.end.to_offset(&file.source);
+            let old_source = file.source.clone();
 
             file.source.replace_range(start..end, &edit.data);
+
+            let reparsed = (edits.len() == 1)
+                .then(|| reparse_incremental(&file.new_tree, &old_source, &file.source, &edit.span))
+                .flatten();
+
+            let (new_tree, errors) = match reparsed {
+                Some(result) => result,
+                None => parse(&file.source),
+            };
+
+            mem::swap(&mut file.old_tree, &mut file.new_tree);
+            file.new_tree = new_tree;
+            file.errors = errors;
         }
+        file.revision += 1;
         Some(file)
     }
 
@@ -112,34 +134,79 @@ impl Env {
     pub fn update_file(&mut self, id: Id<id::File>, source: String) {
         let file = self.file_storage.get_mut(&id).unwrap();
         file.source = source;
+        file.revision += 1;
+    }
+
+    /// Returns `true` if `id` needs to be recompiled: its own revision moved since the last
+    /// time it was analyzed, or one of the dependency revisions it was analyzed against has
+    /// since advanced.
+    fn is_dirty(&self, id: Id<id::File>) -> bool {
+        let Some(file) = self.file_storage.get(&id) else {
+            return true;
+        };
+
+        if file.revision != file.compiled_revision {
+            return true;
+        }
+
+        file.dep_revisions.iter().any(|(dep, revision)| {
+            self.file_storage
+                .get(dep)
+                .map(|dep_file| dep_file.revision != *revision)
+                .unwrap_or(true)
+        })
+    }
+
+    /// Records the revision `id` was analyzed at, along with a snapshot of the revisions of
+    /// everything it imports, so that a later `compile` can tell whether either has moved.
+    fn record_revisions(&mut self, id: Id<id::File>, imports: &HashSet<Id<id::File>>) {
+        let dep_revisions = imports
+            .iter()
+            .filter_map(|dep| self.file_storage.get(dep).map(|file| (*dep, file.revision)))
+            .collect();
+
+        let file = self.file_storage.get_mut(&id).unwrap();
+        file.dep_revisions = dep_revisions;
+        file.compiled_revision = file.revision;
     }
 
     /// Compiles a file and its dependencies asynchronously.
+    ///
+    /// This is a salsa-style incremental recompile: a file is only re-analyzed when its own
+    /// revision or a transitive dependency's revision has advanced since it was last
+    /// compiled, so repeated calls after unrelated edits are cheap. `visited` is reset at the
+    /// start of each call and only tracks progress within this pass, not across calls.
     pub async fn compile(&mut self, id: Id<id::File>) -> Option<()> {
-        if self.visited.contains(&id) {
-            return None;
-        }
-        self.visited.insert(id);
+        self.visited.clear();
 
         let mut stack = vec![id];
-        let mut visited = HashSet::new();
+        let mut seen = HashSet::new();
         let mut to_update = HashSet::new();
 
         while let Some(current_id) = stack.pop() {
-            if visited.contains(&current_id) {
+            if seen.contains(&current_id) {
                 continue;
             }
+            seen.insert(current_id);
+            self.visited.insert(current_id);
+
+            let dependents = self.created.get_dependents(current_id);
+            stack.extend(dependents.iter().map(|(id, _)| *id));
 
-            visited.insert(current_id);
+            if !self.is_dirty(current_id) {
+                continue;
+            }
 
             let (program, imports) = self.precompile(current_id).await?;
             let new_imports = self.process_imports(&program).await;
-            self.update_file_imports(current_id, new_imports.clone(), program);
-            let changed = self.update_relations(current_id, &imports, &new_imports);
+            let new_import_ids: HashSet<Id<id::File>> = new_imports.keys().cloned().collect();
+            self.update_file_imports(current_id, new_imports, program);
+            let changed = self.update_relations(current_id, &imports, &new_import_ids);
+            self.record_revisions(current_id, &new_import_ids);
 
             to_update.insert(current_id);
 
-            for (id, _) in self.created.get_dependents(current_id) {
+            for (id, _) in dependents {
                 to_update.insert(id);
             }
 
@@ -205,17 +272,18 @@ impl Env {
         Some(source)
     }
 
-    /// Processes imports in a program asynchronously.
+    /// Processes imports in a program asynchronously, mapping each imported file to the
+    /// namespace it is reachable under (see [`Env::import_namespace`]).
     async fn process_imports(
         &mut self,
         program: &crate::r#abstract::Program,
-    ) -> HashSet<Id<id::File>> {
-        let mut new_imports = HashSet::new();
+    ) -> HashMap<Id<id::File>, String> {
+        let mut new_imports = HashMap::new();
 
         for top_level in &program.vec {
             if let TopLevelKind::Require(req) = &top_level.data {
                 if let Some(id) = self.process_required(req).await {
-                    new_imports.insert(id);
+                    new_imports.insert(id, Self::import_namespace(req));
                 }
             }
         }
@@ -223,6 +291,34 @@ impl Env {
         new_imports
     }
 
+    /// The namespace a `Require` is reachable under for qualified references
+    /// (`alias/name`): its explicit alias if bound, otherwise the full required path with its
+    /// extension stripped (e.g. `"./a/utils.ff"` becomes `a/utils`).
+    ///
+    /// Using the whole path rather than just the file stem keeps unaliased imports from
+    /// different directories distinct — `"./a/utils.ff"` and `"./b/utils.ff"` would otherwise
+    /// both default to the unqualifiable namespace `utils`, leaving no way to disambiguate a
+    /// same-named export between them short of adding an alias.
+    fn import_namespace(req: &crate::r#abstract::Require) -> String {
+        if let Some(alias) = &req.alias {
+            return alias.data.clone();
+        }
+
+        let path_str = &req.name.data[1..req.name.data.len() - 1];
+        let path = PathBuf::from(path_str).with_extension("");
+
+        path.components()
+            .filter(|component| {
+                !matches!(
+                    component,
+                    std::path::Component::CurDir | std::path::Component::ParentDir
+                )
+            })
+            .map(|component| component.as_os_str().to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
     /// Updates file relations after compilation.
     fn update_relations(
         &mut self,
@@ -230,6 +326,8 @@ impl Env {
         old_imports: &HashSet<Id<id::File>>,
         new_imports: &HashSet<Id<id::File>>,
     ) -> HashSet<Id<id::File>> {
+        // Same dependency-graph shape as before: `update_file_imports` tracks the namespace
+        // each import is reachable under separately, in `file.import_namespaces`.
         let mut affected_files = HashSet::new();
 
         for removed in old_imports.difference(new_imports) {
@@ -249,32 +347,42 @@ impl Env {
     fn update_file_imports(
         &mut self,
         id: Id<id::File>,
-        new_imports: HashSet<Id<id::File>>,
+        new_imports: HashMap<Id<id::File>, String>,
         program: Program,
     ) {
         let file = self.file_storage.get_mut(&id).unwrap();
-        file.imports = new_imports;
+        file.imports = new_imports.keys().cloned().collect();
+        file.import_namespaces = new_imports;
         file.ast = program;
     }
 
     /// Updates file errors and imports after compilation.
     async fn update_file_errors(&mut self, id: Id<id::File>) {
         let file = self.file_storage.get_mut(&id).unwrap();
-        let imps = file.imports.clone();
+        let imps = file.import_namespaces.clone();
 
         let mut errored_defs = HashSet::new();
 
         let mut tracker = ScopeTracker::new(file.ast.span.clone(), id);
         tracker.register_program(&mut file.ast);
 
-        for id in imps {
-            let file = self.file_storage.get_mut(&id).unwrap();
+        for (import_id, namespace) in imps {
+            let file = self.file_storage.get_mut(&import_id).unwrap();
             for name in &file.names {
+                // Unqualified: lets a reference resolve by short name alone, same as before.
                 tracker
                     .imported
                     .entry(name.data.clone())
                     .or_default()
-                    .push((id, name.clone()));
+                    .push((import_id, name.clone()));
+
+                // Qualified: always available as `namespace/name`, even when another import
+                // (or a local definition) shares the short name.
+                tracker
+                    .imported
+                    .entry(format!("{namespace}/{}", name.data))
+                    .or_default()
+                    .push((import_id, name.clone()));
             }
         }
 
@@ -282,19 +390,36 @@ impl Env {
 
         for (_, occs) in &tracker.defined {
             if occs.len() > 1 {
-                for occ in occs {
+                let first = &occs[0];
+                errored_defs.insert(first.span.clone());
+                for occ in &occs[1..] {
                     errored_defs.insert(occ.span.clone());
-                    file.errors
-                        .push(Error::new("duplicated function.", occ.span.clone()));
-                }
-            } else {
-                let name = &occs[0];
-                errored_defs.insert(name.span.clone());
-                if tracker.imported.get(&occs[0].data).is_some() {
-                    file.errors
-                        .push(Error::new("duplicated function.", name.span.clone()));
+                    file.errors.push(
+                        Error::new("duplicated function.", occ.span.clone())
+                            .with_code("firefly::duplicate-function")
+                            .with_related("first defined here", first.span.clone()),
+                    );
                 }
             }
+            // A local definition sharing a name with an import is not a conflict: the local
+            // scope wins and the import stays reachable qualified. Real ambiguity is only
+            // between two imports exporting the same unqualified name, and only when that
+            // name is actually referenced unqualified — `tracker.ambiguous_imports` is
+            // populated by `check_program` below for exactly that case.
+        }
+
+        for (name, (on, occurrences)) in &tracker.ambiguous_imports {
+            errored_defs.insert(on.clone());
+            file.errors.push(
+                Error::new(
+                    format!(
+                        "`{name}` is ambiguous between {} imports; qualify it as `<module>/{name}`.",
+                        occurrences.len()
+                    ),
+                    on.clone(),
+                )
+                .with_code("firefly::ambiguous-import"),
+            );
         }
 
         let file = self.file_storage.get_mut(&id).unwrap();
@@ -308,19 +433,59 @@ impl Env {
 
         file.scopes = tracker.scopes.finish();
 
-        for (_, instances) in tracker.unbound {
-            for (on, instance) in instances {
-                errored_defs.insert(on.clone());
-                file.errors.push(Error::new(
-                    format!("cannot find variable `{}`.", instance.data),
-                    instance.span,
-                ));
+        for (name, (on, occurrences)) in &tracker.ambiguous_imports {
+            errored_defs.insert(on.clone());
+            file.errors.push(
+                Error::new(
+                    format!(
+                        "`{name}` is ambiguous between {} imports; qualify it as `<module>/{name}`.",
+                        occurrences.len()
+                    ),
+                    on.clone(),
+                )
+                .with_code("firefly::ambiguous-import"),
+            );
+        }
+
+        let unbound = tracker
+            .unbound
+            .into_values()
+            .flatten()
+            .collect::<Vec<_>>();
+
+        for (on, instance) in unbound {
+            errored_defs.insert(on.clone());
+
+            let available = self
+                .available_names_in_point(id, instance.span.start.clone())
+                .await;
+
+            let mut error = Error::new(
+                format!("cannot find variable `{}`.", instance.data),
+                instance.span.clone(),
+            )
+            .with_code("firefly::unbound-variable");
+
+            if let Some(suggestion) = closest_name(&instance.data, available.iter()) {
+                error = error.with_help(format!("did you mean `{suggestion}`?"));
             }
+
+            let file = self.file_storage.get_mut(&id).unwrap();
+            file.errors.push(error);
         }
 
+        let file = self.file_storage.get_mut(&id).unwrap();
         file.errored_tl = errored_defs;
     }
 
+    /// Returns the diagnostics currently recorded for `id`.
+    pub fn diagnostics(&self, id: Id<id::File>) -> &[Error] {
+        self.file_storage
+            .get(&id)
+            .map(|file| file.errors.as_slice())
+            .unwrap_or_default()
+    }
+
     /// Updates file errors and imports after compilation.
     pub async fn available_names_in_point(
         &mut self,
@@ -328,7 +493,7 @@ impl Env {
         point: Point,
     ) -> HashSet<String> {
         let file = self.file_storage.get_mut(&id).unwrap();
-        let imps = file.imports.clone();
+        let imps = file.import_namespaces.clone();
 
         let mut tracker = HashSet::<String>::new();
         let mut scopes = Vec::new();
@@ -343,11 +508,565 @@ impl Env {
             tracker.extend(res)
         }
 
-        for id in imps {
-            let file = self.file_storage.get_mut(&id).unwrap();
-            tracker.extend(file.names.clone().into_iter().map(|x| x.data))
+        for (import_id, namespace) in imps {
+            let file = self.file_storage.get_mut(&import_id).unwrap();
+            for name in file.names.clone() {
+                tracker.insert(name.data.clone());
+                tracker.insert(format!("{namespace}/{}", name.data));
+            }
         }
 
         tracker
     }
+
+    /// Resolves the symbol spanning `point`: a local binding if `point` falls within a
+    /// tracked scope, otherwise a top-level definition whose span contains `point`.
+    fn symbol_at(&self, id: Id<id::File>, point: &Point) -> Option<String> {
+        let file = self.file_storage.get(&id)?;
+        let span = Span::new(point.clone(), point.clone());
+
+        let mut scopes = Vec::new();
+        file.scopes.accumulate(&span, &mut scopes);
+        for scope in &scopes {
+            for (name, occurrences) in &scope.data.vars {
+                if occurrences.iter().any(|occ| occ.contains(&span)) {
+                    return Some(name.clone());
+                }
+            }
+        }
+
+        file.names
+            .iter()
+            .find(|def| def.span.contains(&span))
+            .map(|def| def.data.clone())
+    }
+
+    /// Resolves the symbol under `point` in `id` to its definition, then returns every
+    /// occurrence of it across the file graph: the top-level definition site, every local
+    /// binding use recorded in `scopes`, and every usage site in a dependent file whose
+    /// `import_namespaces` entry points back at the definer.
+    ///
+    /// `id`/`point` only locate *which* symbol is meant — the actual walk happens from the
+    /// symbol's definer (resolved via [`Env::definition_site`]), not from `id` itself. Calling
+    /// this from a file that merely *consumes* the symbol (imports it from elsewhere) must
+    /// still find the definition and every other consumer, not just the local call site.
+    pub async fn find_references(
+        &mut self,
+        id: Id<id::File>,
+        point: Point,
+    ) -> Vec<(Id<id::File>, Span)> {
+        let Some(name) = self.symbol_at(id, &point) else {
+            return Vec::new();
+        };
+        let (def_id, _) = self.definition_site(id, &point).unwrap_or((id, point));
+
+        let mut references = Vec::new();
+
+        let file = self.file_storage.get(&def_id).unwrap();
+        for def in &file.names {
+            if def.data == name {
+                references.push((def_id, def.span.clone()));
+            }
+        }
+
+        let mut scopes = Vec::new();
+        file.scopes.accumulate(&file.ast.span, &mut scopes);
+        for scope in scopes {
+            if let Some(occurrences) = scope.data.vars.get(&name) {
+                references.extend(occurrences.iter().map(|span| (def_id, span.clone())));
+            }
+        }
+
+        for (dep_id, _) in self.created.get_dependents(def_id) {
+            let Some(dep_file) = self.file_storage.get(&dep_id) else {
+                continue;
+            };
+            let Some(namespace) = dep_file.import_namespaces.get(&def_id) else {
+                continue;
+            };
+
+            let qualified = format!("{namespace}/{name}");
+            let mut dep_scopes = Vec::new();
+            dep_file.scopes.accumulate(&dep_file.ast.span, &mut dep_scopes);
+
+            for scope in dep_scopes {
+                for key in [&name, &qualified] {
+                    if let Some(occurrences) = scope.data.vars.get(key) {
+                        references.extend(occurrences.iter().map(|span| (dep_id, span.clone())));
+                    }
+                }
+            }
+        }
+
+        references
+    }
+
+    /// Resolves the symbol under `point` in `id` to its definition site: a local definition
+    /// in `id` itself if one matches, otherwise the import that exports it. Falls back to
+    /// `(id, point)` if neither is found (e.g. a local binding with no top-level definition).
+    fn definition_site(&self, id: Id<id::File>, point: &Point) -> Option<(Id<id::File>, Point)> {
+        let name = self.symbol_at(id, point)?;
+        let file = self.file_storage.get(&id)?;
+
+        if let Some(def) = file.names.iter().find(|def| def.data == name) {
+            return Some((id, def.span.start.clone()));
+        }
+
+        for dep_id in file.import_namespaces.keys() {
+            let Some(dep_file) = self.file_storage.get(dep_id) else {
+                continue;
+            };
+            if let Some(def) = dep_file.names.iter().find(|def| def.data == name) {
+                return Some((*dep_id, def.span.start.clone()));
+            }
+        }
+
+        Some((id, point.clone()))
+    }
+
+    /// Renames every occurrence of the symbol under `point` to `new_name`, grouped by the
+    /// file each edit belongs to so the result can be fed straight into [`Env::apply_edits`]
+    /// per file. Returns `None` if `new_name` is already visible at the *definition's* scope
+    /// — resolved via [`Env::definition_site`], not wherever `point` happens to be, since a
+    /// rename invoked from one reference occurrence shouldn't be judged against a different
+    /// scope's visible names.
+    pub async fn rename(
+        &mut self,
+        id: Id<id::File>,
+        point: Point,
+        new_name: String,
+    ) -> Option<HashMap<Id<id::File>, Vec<Spanned<String>>>> {
+        let (def_id, def_point) = self.definition_site(id, &point)?;
+
+        let visible = self.available_names_in_point(def_id, def_point).await;
+        if visible.contains(&new_name) {
+            return None;
+        }
+
+        let mut edits: HashMap<Id<id::File>, Vec<Spanned<String>>> = HashMap::new();
+        for (ref_id, span) in self.find_references(def_id, def_point).await {
+            edits
+                .entry(ref_id)
+                .or_default()
+                .push(Spanned::new(new_name.clone(), span));
+        }
+
+        Some(edits)
+    }
+
+    /// Lowers `id`'s `main` definition to an HVM term: ensures `id` and its import closure are
+    /// compiled, walks their ASTs in true dependency order (every file after everything it
+    /// imports) so each file's definitions are resolvable by name (unqualified and qualified
+    /// under the importer's namespace) by the time a dependent is lowered, then returns the
+    /// entry point's `main` definition.
+    ///
+    /// Only `main`'s term is returned. Its references to other definitions, local or imported,
+    /// stay `Ref`s by name, and the rules they name are not returned with it.
+    ///
+    /// Bails out with the closure's existing diagnostics rather than attempting to lower a
+    /// file that already failed analysis — in particular this catches an unresolved
+    /// `firefly::ambiguous-import`, which would otherwise make `visible`'s unqualified entry
+    /// for the colliding name pick one of the imports nondeterministically.
+    pub async fn lower(&mut self, id: Id<id::File>) -> Result<hvm::Term, Vec<Error>> {
+        self.compile(id).await;
+
+        let QueryResult::Files(imports) = self.query(Query::TransitiveImports(id)) else {
+            unreachable!("TransitiveImports always answers with Files")
+        };
+
+        let order = self.topological_order(id, &imports);
+
+        let existing_errors: Vec<Error> = order
+            .iter()
+            .flat_map(|file_id| self.diagnostics(*file_id).to_vec())
+            .collect();
+        if !existing_errors.is_empty() {
+            return Err(existing_errors);
+        }
+
+        let mut rules: HashMap<Id<id::File>, HashMap<String, hvm::Term>> = HashMap::new();
+
+        for current in order {
+            let Some(file) = self.file_storage.get(&current) else {
+                continue;
+            };
+
+            let mut visible = HashMap::new();
+            for (dep_id, namespace) in &file.import_namespaces {
+                if let Some(dep_rules) = rules.get(dep_id) {
+                    for (name, term) in dep_rules {
+                        visible.insert(name.clone(), term.clone());
+                        visible.insert(format!("{namespace}/{name}"), term.clone());
+                    }
+                }
+            }
+
+            rules.insert(current, lower_program(&file.ast, &visible)?);
+        }
+
+        let file = self.file_storage.get(&id).unwrap();
+        rules
+            .remove(&id)
+            .and_then(|defs| defs.get("main").cloned())
+            .ok_or_else(|| {
+                vec![Error::new(
+                    "no `main` definition to lower to an entry point.",
+                    file.ast.span.clone(),
+                )
+                .with_code("firefly::codegen-no-entry-point")]
+            })
+    }
+
+    /// Answers a structured question about the session's file graph. See [`Query`] for what's
+    /// available; new per-file predicates should be added as [`Filter`] variants rather than
+    /// new `Env` methods.
+    pub fn query(&self, query: Query) -> QueryResult {
+        match query {
+            Query::Filter(filter) => QueryResult::Files(
+                self.file_storage
+                    .ids()
+                    .filter(|id| self.matches(*id, &filter))
+                    .collect(),
+            ),
+            Query::TransitiveImports(id) => QueryResult::Files(self.transitive_imports(id)),
+            Query::DefinitionsOf(name) => QueryResult::Definitions(self.definitions_of(&name)),
+            Query::Cycles => QueryResult::Cycles(self.cycles()),
+        }
+    }
+
+    /// Evaluates a [`Filter`] against a single file.
+    fn matches(&self, id: Id<id::File>, filter: &Filter) -> bool {
+        match filter {
+            Filter::PathGlob(pattern) => self
+                .files
+                .get(&id)
+                .map(|path| glob_match(pattern, &path.to_string_lossy()))
+                .unwrap_or(false),
+            Filter::HasErrors => self
+                .file_storage
+                .get(&id)
+                .map(|file| !file.errors.is_empty())
+                .unwrap_or(false),
+            Filter::Imports(target) => self
+                .file_storage
+                .get(&id)
+                .map(|file| file.imports.contains(target))
+                .unwrap_or(false),
+            Filter::And(a, b) => self.matches(id, a) && self.matches(id, b),
+            Filter::Or(a, b) => self.matches(id, a) || self.matches(id, b),
+            Filter::Not(a) => !self.matches(id, a),
+        }
+    }
+
+    /// Sorts `id` and `imports` in true dependency order — every file after everything it
+    /// (transitively) imports — via post-order DFS over `file.imports` edges. A file that
+    /// only reaches the closure through a multi-hop chain is still ordered correctly, unlike
+    /// sorting by direct import count.
+    fn topological_order(&self, id: Id<id::File>, imports: &[Id<id::File>]) -> Vec<Id<id::File>> {
+        let mut order = Vec::new();
+        let mut visited = HashSet::new();
+
+        self.visit_topological(id, &mut visited, &mut order);
+        for dep in imports {
+            self.visit_topological(*dep, &mut visited, &mut order);
+        }
+
+        order
+    }
+
+    fn visit_topological(
+        &self,
+        current: Id<id::File>,
+        visited: &mut HashSet<Id<id::File>>,
+        order: &mut Vec<Id<id::File>>,
+    ) {
+        if !visited.insert(current) {
+            return;
+        }
+
+        if let Some(file) = self.file_storage.get(&current) {
+            for dep in file.imports.clone() {
+                self.visit_topological(dep, visited, order);
+            }
+        }
+
+        order.push(current);
+    }
+
+    /// Every file `id` depends on, directly or transitively.
+    fn transitive_imports(&self, id: Id<id::File>) -> Vec<Id<id::File>> {
+        let mut stack = vec![id];
+        let mut seen = HashSet::new();
+
+        while let Some(current) = stack.pop() {
+            let Some(file) = self.file_storage.get(&current) else {
+                continue;
+            };
+            for import in &file.imports {
+                if seen.insert(*import) {
+                    stack.push(*import);
+                }
+            }
+        }
+
+        seen.into_iter().collect()
+    }
+
+    /// Every top-level definition of `name` across every file in the session.
+    fn definitions_of(&self, name: &str) -> Vec<Definition> {
+        let mut definitions = Vec::new();
+
+        for id in self.file_storage.ids() {
+            let Some(file) = self.file_storage.get(&id) else {
+                continue;
+            };
+            for def in &file.names {
+                if def.data == name {
+                    definitions.push(Definition {
+                        file: id,
+                        span: def.span.clone(),
+                    });
+                }
+            }
+        }
+
+        definitions
+    }
+
+    /// Dependency cycles among `Require`s, found via DFS over each file's import edges.
+    ///
+    /// Every node on a cycle is used as a DFS root, so the same cycle would otherwise be
+    /// reported once per node on it (rotated differently each time); each is canonicalized
+    /// via [`canonical_rotation`] and deduped before returning.
+    fn cycles(&self) -> Vec<Cycle> {
+        let mut found = Vec::new();
+
+        for start in self.file_storage.ids() {
+            let mut path = vec![start];
+            let mut on_path = HashSet::from([start]);
+            self.find_cycles_from(start, &mut path, &mut on_path, &mut found);
+        }
+
+        let mut seen = HashSet::new();
+        let mut cycles = Vec::new();
+
+        for cycle in found {
+            let canonical = canonical_rotation(&cycle.0);
+            let key = canonical
+                .iter()
+                .map(|id| format!("{id:?}"))
+                .collect::<Vec<_>>()
+                .join(",");
+
+            if seen.insert(key) {
+                cycles.push(Cycle(canonical));
+            }
+        }
+
+        cycles
+    }
+
+    fn find_cycles_from(
+        &self,
+        current: Id<id::File>,
+        path: &mut Vec<Id<id::File>>,
+        on_path: &mut HashSet<Id<id::File>>,
+        cycles: &mut Vec<Cycle>,
+    ) {
+        let Some(file) = self.file_storage.get(&current) else {
+            return;
+        };
+
+        for import in file.imports.clone() {
+            if import == path[0] {
+                cycles.push(Cycle(path.clone()));
+                continue;
+            }
+            if on_path.insert(import) {
+                path.push(import);
+                self.find_cycles_from(import, path, on_path, cycles);
+                path.pop();
+                on_path.remove(&import);
+            }
+        }
+    }
+}
+
+/// Rotates `cycle` so it starts at its lexicographically-least element (by debug
+/// representation, since `Id` doesn't otherwise expose an ordering). Two rotations of the
+/// same cycle canonicalize to the same sequence, so callers can dedupe with a plain
+/// `HashSet` of canonical forms.
+fn canonical_rotation(cycle: &[Id<id::File>]) -> Vec<Id<id::File>> {
+    let Some(min_index) = cycle
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, id)| format!("{id:?}"))
+        .map(|(index, _)| index)
+    else {
+        return Vec::new();
+    };
+
+    cycle[min_index..]
+        .iter()
+        .chain(&cycle[..min_index])
+        .cloned()
+        .collect()
+}
+
+/// A minimal glob matcher supporting `*` (any run of characters within a path segment) and
+/// `**` (any run of characters, including path separators).
+fn glob_match(pattern: &str, text: &str) -> bool {
+    fn inner(pattern: &[u8], text: &[u8]) -> bool {
+        match pattern.first() {
+            None => text.is_empty(),
+            Some(b'*') if pattern.get(1) == Some(&b'*') => {
+                let rest = &pattern[2..];
+                (0..=text.len()).any(|i| inner(rest, &text[i..]))
+            }
+            Some(b'*') => {
+                let rest = &pattern[1..];
+                (0..=text.len())
+                    .take_while(|&i| i == 0 || text[i - 1] != b'/')
+                    .any(|i| inner(rest, &text[i..]))
+            }
+            Some(&c) => text.first() == Some(&c) && inner(&pattern[1..], &text[1..]),
+        }
+    }
+
+    inner(pattern.as_bytes(), text.as_bytes())
+}
+
+/// Finds the in-scope name closest to `target` by edit distance, for the "did you mean...?"
+/// hint on an unbound-variable diagnostic. Returns `None` if nothing is within a plausible
+/// typo distance (more than a third of the target's length, floored at one edit).
+fn closest_name<'a>(target: &str, candidates: impl Iterator<Item = &'a String>) -> Option<&'a str> {
+    let max_distance = (target.chars().count() / 3).max(1);
+
+    candidates
+        .map(|candidate| (candidate, edit_distance(target, candidate)))
+        .filter(|(_, distance)| *distance <= max_distance)
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(candidate, _)| candidate.as_str())
+}
+
+/// Classic Levenshtein distance between two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cur = row[j + 1];
+            row[j + 1] = if ca == cb {
+                prev
+            } else {
+                1 + prev.min(row[j]).min(row[j + 1])
+            };
+            prev = cur;
+        }
+    }
+
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashSet, path::PathBuf};
+
+    use super::Env;
+    use crate::{r#abstract::Require, span::{Span, Spanned}};
+
+    fn new_env() -> Env {
+        let (sender, _receiver) = tokio::sync::mpsc::channel(1);
+        Env::new(sender)
+    }
+
+    fn require(name: &str, alias: Option<&str>) -> Require {
+        Require {
+            name: Spanned::new(name.to_string(), Span::empty()),
+            alias: alias.map(|alias| Spanned::new(alias.to_string(), Span::empty())),
+        }
+    }
+
+    #[test]
+    fn import_namespace_differs_across_directories_without_an_alias() {
+        // Both requires export a same-named `utils`; defaulting to the file stem alone would
+        // collide and leave no way to qualify one from the other.
+        let a = require("\"./a/utils.ff\"", None);
+        let b = require("\"./b/utils.ff\"", None);
+
+        assert_ne!(Env::import_namespace(&a), Env::import_namespace(&b));
+        assert_eq!(Env::import_namespace(&a), "a/utils");
+        assert_eq!(Env::import_namespace(&b), "b/utils");
+    }
+
+    #[test]
+    fn import_namespace_prefers_an_explicit_alias() {
+        let aliased = require("\"./a/utils.ff\"", Some("u"));
+        assert_eq!(Env::import_namespace(&aliased), "u");
+    }
+
+    #[test]
+    fn is_dirty_tracks_its_own_revision_and_its_dependencies() {
+        let mut env = new_env();
+        let id = env.add_file(PathBuf::from("/a.ff"));
+        let dep = env.add_file(PathBuf::from("/b.ff"));
+        let imports = HashSet::from([dep]);
+
+        // Never recorded as compiled yet: always dirty.
+        assert!(env.is_dirty(id));
+
+        env.record_revisions(id, &imports);
+        assert!(!env.is_dirty(id), "freshly recorded revisions should be clean");
+
+        env.update_file(id, "edited".to_string());
+        assert!(env.is_dirty(id), "the file's own revision moving should mark it dirty");
+
+        env.record_revisions(id, &imports);
+        assert!(!env.is_dirty(id));
+
+        env.update_file(dep, "edited dep".to_string());
+        assert!(
+            env.is_dirty(id),
+            "a dependency's revision moving should mark it dirty too, even though `id` itself didn't change"
+        );
+    }
+
+    #[test]
+    fn closest_name_suggests_a_nearby_in_scope_name() {
+        let candidates = vec!["length".to_string(), "map".to_string(), "filter".to_string()];
+
+        assert_eq!(
+            super::closest_name("lenght", candidates.iter()),
+            Some("length")
+        );
+        assert_eq!(super::closest_name("zzzzzzzzzz", candidates.iter()), None);
+    }
+
+    #[test]
+    fn glob_match_supports_star_and_double_star() {
+        assert!(super::glob_match("src/*.ff", "src/main.ff"));
+        assert!(!super::glob_match("src/*.ff", "src/lib/main.ff"));
+        assert!(super::glob_match("src/**/*.ff", "src/lib/main.ff"));
+        assert!(!super::glob_match("src/*.ff", "src/main.rs"));
+    }
+
+    #[test]
+    fn canonical_rotation_dedupes_equivalent_rotations() {
+        use crate::{file::File, id, storage::Storage, syntax::SyntaxNode};
+
+        let mut storage: Storage<id::File, File> = Storage::default();
+        let a = storage.add(File::new(SyntaxNode::empty(), "".to_string(), Vec::new()));
+        let b = storage.add(File::new(SyntaxNode::empty(), "".to_string(), Vec::new()));
+        let c = storage.add(File::new(SyntaxNode::empty(), "".to_string(), Vec::new()));
+
+        let rotated_once = super::canonical_rotation(&[b, c, a]);
+        let rotated_twice = super::canonical_rotation(&[c, a, b]);
+
+        assert_eq!(super::canonical_rotation(&[a, b, c]), rotated_once);
+        assert_eq!(rotated_once, rotated_twice);
+    }
 }
@@ -0,0 +1,158 @@
+//! Diagnostics produced while parsing and analyzing a file.
+
+use std::fmt;
+
+use miette::{Diagnostic, LabeledSpan};
+
+use crate::span::Span;
+
+/// How serious a diagnostic is, mirroring the levels an editor renders differently (a red
+/// squiggle, a yellow one, or a hint underline).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Hint,
+}
+
+/// A secondary span related to the main diagnostic, carrying its own label (e.g. "first
+/// defined here" when reporting a duplicate).
+#[derive(Debug, Clone)]
+pub struct Related {
+    pub label: String,
+    pub span: Span,
+}
+
+/// A diagnostic produced while parsing or analyzing a file.
+#[derive(Debug, Clone)]
+pub struct Error {
+    pub message: String,
+    pub span: Span,
+    pub severity: Severity,
+    pub code: String,
+    pub related: Vec<Related>,
+    pub help: Option<String>,
+}
+
+impl Error {
+    /// Creates a plain error-severity diagnostic under the generic `"firefly::error"` code.
+    pub fn new(message: impl Into<String>, span: Span) -> Self {
+        Self {
+            message: message.into(),
+            span,
+            severity: Severity::Error,
+            code: "firefly::error".to_string(),
+            related: Vec::new(),
+            help: None,
+        }
+    }
+
+    /// Sets the stable diagnostic code (e.g. `"firefly::duplicate-function"`).
+    pub fn with_code(mut self, code: impl Into<String>) -> Self {
+        self.code = code.into();
+        self
+    }
+
+    /// Sets the severity.
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    /// Attaches a related, labeled span, e.g. pointing at a conflicting definition.
+    pub fn with_related(mut self, label: impl Into<String>, span: Span) -> Self {
+        self.related.push(Related {
+            label: label.into(),
+            span,
+        });
+        self
+    }
+
+    /// Attaches a help message suggesting a fix.
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl Diagnostic for Error {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        Some(Box::new(&self.code))
+    }
+
+    fn severity(&self) -> Option<miette::Severity> {
+        Some(match self.severity {
+            Severity::Error => miette::Severity::Error,
+            Severity::Warning => miette::Severity::Warning,
+            Severity::Hint => miette::Severity::Advice,
+        })
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.help
+            .as_ref()
+            .map(|help| Box::new(help) as Box<dyn fmt::Display + 'a>)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let primary = LabeledSpan::underline(self.span.clone().into());
+        let related = self
+            .related
+            .iter()
+            .map(|r| LabeledSpan::new_with_span(Some(r.label.clone()), r.span.clone().into()));
+
+        Some(Box::new(std::iter::once(primary).chain(related)))
+    }
+}
+
+/// Pairs a diagnostic with the source text of the file it belongs to, so a frontend can
+/// render it with `miette`'s source snippets without `Error` itself owning a copy of the
+/// file it came from.
+pub struct WithSource<'a> {
+    pub error: &'a Error,
+    pub source: &'a str,
+}
+
+impl fmt::Debug for WithSource<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.error, f)
+    }
+}
+
+impl fmt::Display for WithSource<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.error, f)
+    }
+}
+
+impl std::error::Error for WithSource<'_> {}
+
+impl Diagnostic for WithSource<'_> {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.error.code()
+    }
+
+    fn severity(&self) -> Option<miette::Severity> {
+        self.error.severity()
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.error.help()
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        self.error.labels()
+    }
+
+    fn source_code(&self) -> Option<&dyn miette::SourceCode> {
+        Some(&self.source)
+    }
+}
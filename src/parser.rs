@@ -91,14 +91,43 @@ impl<'input> Parser<'input> {
         self.finish_node(span);
     }
 
-    /// Records a parsing error.
+    /// Recovers from a token the grammar can't handle at this position: wraps it (and
+    /// everything up to the next balanced boundary) in a `SyntaxKind::Error` node and records
+    /// a diagnostic, instead of panicking. Resynchronizing on balanced `(`/`)` depth means a
+    /// malformed expression doesn't poison the rest of the file, so later definitions still
+    /// produce a usable CST and scope info.
+    ///
+    /// A fresh `(` encountered at depth 0 ends recovery instead of being swallowed as nested
+    /// garbage: it isn't part of the broken form, it's the start of the next one, and the
+    /// caller (which re-checks the current token after `error` returns) is what parses it into
+    /// its own node.
     fn error(&mut self, message: impl Into<String>) {
         let span = self.current_span();
         self.start_node(SyntaxKind::Error);
         self.errors.push(Error::new(message.into(), span));
-        let span = self.current_span();
         self.bump();
-        self.finish_node(span);
+
+        let mut depth = 0i32;
+        loop {
+            match self.current() {
+                None => break,
+                Some(SyntaxKind::LPar) if depth == 0 => break,
+                Some(SyntaxKind::LPar) => {
+                    depth += 1;
+                    self.bump();
+                }
+                Some(SyntaxKind::RPar) if depth > 0 => {
+                    depth -= 1;
+                    self.bump();
+                }
+                Some(SyntaxKind::RPar) => break,
+                Some(_) => {
+                    self.bump();
+                }
+            }
+        }
+
+        self.finish_node(self.current_span());
     }
 
     /// Parses a list of expressions.
@@ -162,11 +191,32 @@ impl<'input> Parser<'input> {
                 self.errors
                     .push(Error::new("unfinished string".to_owned(), span));
             }
-            k => todo!("{k:?}"),
+            k => self.error(format!("unexpected token `{k:?}`")),
         }
         Response::Ok
     }
 
+    /// Parses a single expression, without wrapping it in a `Root` node.
+    ///
+    /// Used by incremental reparsing to re-lex just the source slice covering one node,
+    /// rather than the whole file. Returns `None` if the slice doesn't parse as exactly one
+    /// expression — in particular, if tokens remain after it (e.g. an edit split one
+    /// `Identifier` leaf into two tokens), since the fragment would then only cover part of
+    /// the slice and the caller must fall back to a full reparse instead of silently dropping
+    /// the rest.
+    pub fn parse_fragment(mut self) -> Option<(SyntaxNode, Vec<Error>)> {
+        match self.expr() {
+            Response::Ok => {
+                self.skip_whitespace();
+                if self.lexer.peek().is_some() {
+                    return None;
+                }
+                Some((self.builder.finish(self.span.clone()), self.errors))
+            }
+            _ => None,
+        }
+    }
+
     /// Parses the entire input stream and returns the resulting CST and any errors encountered.
     pub fn parse(mut self) -> (SyntaxNode, Vec<Error>) {
         self.start_node(SyntaxKind::Root);
@@ -187,6 +237,87 @@ pub fn parse(code: &str) -> (SyntaxNode, Vec<Error>) {
     Parser::new(Lexer::new(code)).parse()
 }
 
+/// Checks that every `(` in `source` is matched by a `)`, and vice versa.
+fn is_balanced(source: &str) -> bool {
+    let mut depth = 0i32;
+    for ch in source.chars() {
+        match ch {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth < 0 {
+                    return false;
+                }
+            }
+            _ => {}
+        }
+    }
+    depth == 0
+}
+
+/// Finds the smallest node in `old` whose span fully contains `[start, end)` and whose kind
+/// is re-entrant: a [`SyntaxKind::List`], a [`SyntaxKind::String`], or a token leaf.
+fn find_reentrant_node<'a>(old: &'a SyntaxNode, start: usize, end: usize, source: &str) -> Option<&'a SyntaxNode> {
+    old.descendants()
+        .filter(|node| {
+            matches!(
+                node.kind(),
+                SyntaxKind::List | SyntaxKind::String | SyntaxKind::Identifier | SyntaxKind::Number
+            )
+        })
+        .filter(|node| {
+            let span = node.span();
+            span.start.to_offset(source) <= start && end <= span.end.to_offset(source)
+        })
+        .min_by_key(|node| {
+            let span = node.span();
+            span.end.to_offset(source) - span.start.to_offset(source)
+        })
+}
+
+/// Reparses only the subtree touched by a single contiguous edit, reusing the rest of `old`.
+///
+/// `edit` is the edited range expressed in `old_source` offsets; `new_source` is the full
+/// text after the edit has already been applied. Returns `None` when the edit can't be
+/// reused in place — it straddles more than one top-level node, leaves parentheses
+/// unbalanced, the slice doesn't re-lex as exactly one expression (e.g. the edit split one
+/// token into two), or the freshly parsed fragment's kind doesn't match the node it replaces
+/// — in which case the caller should fall back to [`parse`] on the full source.
+pub fn reparse_incremental(
+    old: &SyntaxNode,
+    old_source: &str,
+    new_source: &str,
+    edit: &Span,
+) -> Option<(SyntaxNode, Vec<Error>)> {
+    let old_start = edit.start.to_offset(old_source);
+    let old_end = edit.end.to_offset(old_source);
+    let delta = new_source.len() as isize - old_source.len() as isize;
+
+    let target = find_reentrant_node(old, old_start, old_end, old_source)?;
+    let target_span = target.span();
+    let target_start = target_span.start.to_offset(old_source);
+    let target_end = target_span.end.to_offset(old_source);
+    let shifted_end = (target_end as isize + delta) as usize;
+
+    let slice = new_source.get(target_start..shifted_end)?;
+    if !is_balanced(slice) {
+        return None;
+    }
+
+    let (fragment, mut errors) = Parser::new(Lexer::new(slice)).parse_fragment()?;
+    if fragment.kind() != target.kind() {
+        return None;
+    }
+
+    for error in &mut errors {
+        error.span.start.shift(target_start as isize);
+        error.span.end.shift(target_start as isize);
+    }
+
+    let new_root = old.splice(target, fragment, delta);
+    Some((new_root, errors))
+}
+
 #[cfg(test)]
 mod test {
     use crate::lexer::Lexer;
@@ -202,4 +333,39 @@ mod test {
         println!("errors = {errors:?}");
         println!("{}", syntax);
     }
+
+    #[test]
+    fn parse_fragment_rejects_trailing_tokens() {
+        // "a b" re-lexes as two `Identifier` tokens; `expr()` only consumes the first one,
+        // so accepting this fragment would silently drop " b" from the incrementally
+        // reparsed tree instead of falling back to a full reparse.
+        let trailing = Parser::new(Lexer::new("a b")).parse_fragment();
+        assert!(trailing.is_none());
+
+        let (node, errors) = Parser::new(Lexer::new("a")).parse_fragment().unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(node.kind(), super::SyntaxKind::Identifier);
+    }
+
+    #[test]
+    fn recovers_from_a_stray_top_level_rparen_instead_of_panicking() {
+        let (_, errors) = super::parse(")");
+        assert!(!errors.is_empty());
+    }
+
+    #[test]
+    fn recovers_and_keeps_parsing_after_the_resync_boundary() {
+        // A stray `)` at the top level must not swallow the well-formed form that follows it:
+        // the resync loop has to stop at a fresh top-level `(` instead of treating it as more
+        // garbage to consume, or `(def a)` ends up absorbed into the `Error` node instead of
+        // surviving as its own `List`.
+        let (syntax, errors) = super::parse(") (def a)");
+        assert!(!errors.is_empty());
+
+        let lists = syntax
+            .descendants()
+            .filter(|node| node.kind() == super::SyntaxKind::List)
+            .count();
+        assert_eq!(lists, 1, "`(def a)` should survive as its own List node:\n{syntax}");
+    }
 }
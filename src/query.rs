@@ -0,0 +1,67 @@
+//! A tiny composable query vocabulary over the session's file graph.
+//!
+//! Rather than growing `Env` a bespoke method per question ("files with errors", "files
+//! importing X", ...), simple per-file questions are expressed as a [`Filter`] and evaluated
+//! once by `Env::query`; new predicates compose with `and`/`or`/`not` instead of new API.
+//! Questions that aren't a per-file predicate (closures, symbol search, cycle detection) get
+//! their own [`Query`] variant but still funnel through the same entry point and [`QueryResult`].
+
+use crate::{id, span::Span};
+
+/// A predicate evaluated against a single file.
+pub enum Filter {
+    /// The file's path matches a glob pattern (e.g. `"src/**/*.ff"`).
+    PathGlob(String),
+    /// The file currently holds at least one error.
+    HasErrors,
+    /// The file directly imports the given file.
+    Imports(id::Id<id::File>),
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+    Not(Box<Filter>),
+}
+
+impl Filter {
+    pub fn and(self, other: Filter) -> Filter {
+        Filter::And(Box::new(self), Box::new(other))
+    }
+
+    pub fn or(self, other: Filter) -> Filter {
+        Filter::Or(Box::new(self), Box::new(other))
+    }
+
+    pub fn not(self) -> Filter {
+        Filter::Not(Box::new(self))
+    }
+}
+
+/// A question `Env::query` can answer.
+pub enum Query {
+    /// Files matching a composable predicate (reverse deps via `Filter::Imports`, files with
+    /// errors via `Filter::HasErrors`, etc.).
+    Filter(Filter),
+    /// The transitive import closure of a file (every file it depends on, directly or not).
+    TransitiveImports(id::Id<id::File>),
+    /// Every definition site of a symbol name across the whole session.
+    DefinitionsOf(String),
+    /// Dependency cycles among `Require`s.
+    Cycles,
+}
+
+/// One definition site of a symbol, for [`Query::DefinitionsOf`].
+#[derive(Debug, Clone)]
+pub struct Definition {
+    pub file: id::Id<id::File>,
+    pub span: Span,
+}
+
+/// A dependency cycle, as the file ids on the cycle in import order.
+#[derive(Debug, Clone)]
+pub struct Cycle(pub Vec<id::Id<id::File>>);
+
+/// The answer to a [`Query`].
+pub enum QueryResult {
+    Files(Vec<id::Id<id::File>>),
+    Definitions(Vec<Definition>),
+    Cycles(Vec<Cycle>),
+}